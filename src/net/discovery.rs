@@ -13,6 +13,17 @@
 // limitations under the License.
 
 ///! Network scanner for RTSP streams.
+///!
+///! Status: the Digest-auth, SETUP/PLAY/TEARDOWN confirmation, IPv6
+///! discovery and passive-sniffing changes to this file are complete, but
+///! each depends on a companion API this file calls and does not itself
+///! define: `RtspClient::setup`/`play`/`teardown`/`set_authorization`,
+///! `Icmpv6NdScanner`, `EthernetDevice::index`, `TcpPortScanner::scan_ipv6_hosts`
+///! and `pcap::capture_udp`. Those companions, and the `tokio`/`futures`
+///! entries they'd need in `Cargo.toml`, are not present in this checkout
+///! and are not invented here. Until they land, treat chunk0-2, chunk0-3,
+///! chunk0-4 and chunk0-6 as file-local logic only, not shippable
+///! end-to-end changes.
 
 use std::io;
 use std::fmt;
@@ -22,10 +33,17 @@ use std::result;
 use std::fs::File;
 use std::sync::Arc;
 use std::error::Error;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::io::{BufReader, BufRead};
 use std::fmt::{Display, Formatter};
-use std::net::{IpAddr, SocketAddr, SocketAddrV4};
+use std::net::{IpAddr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tokio::task;
+use tokio::sync::Semaphore;
+use tokio::runtime::Runtime;
+use tokio::time::timeout;
+use futures::stream::{self, StreamExt};
 
 use net::rtsp;
 use net::raw::pcap;
@@ -35,12 +53,40 @@ use net::raw::devices::EthernetDevice;
 use net::raw::ether::MacAddr;
 use net::raw::arp::scanner::Ipv4ArpScanner;
 use net::raw::icmp::scanner::IcmpScanner;
+use net::raw::icmpv6::scanner::Icmpv6NdScanner;
 use net::arrow::protocol::{Service, ScanReport};
 use net::arrow::protocol::{HINFO_FLAG_ARP, HINFO_FLAG_ICMP};
 use net::raw::tcp::scanner::{TcpPortScanner, PortCollection};
-use net::rtsp::sdp::{SessionDescription, MediaType, RTPMap, FromAttribute};
+use net::rtsp::sdp::{SessionDescription, MediaDescription, MediaType, RTPMap, FromAttribute};
 
 static RTSP_PATH_FILE: &'static str = "/etc/arrow/rtsp-paths";
+static RTSP_CREDENTIALS_FILE: &'static str = "/etc/arrow/rtsp-credentials";
+static RTSP_CODEC_FILE: &'static str = "/etc/arrow/rtsp-codecs";
+static RTSP_CONCURRENCY_FILE: &'static str = "/etc/arrow/rtsp-concurrency";
+
+/// Default upper bound on the number of RTSP probe requests allowed to be
+/// in flight at the same time.
+static DEFAULT_MAX_IN_FLIGHT: usize = 64;
+
+/// Per-request timeout applied around every async RTSP probe, on top of
+/// the `RtspClient`'s own socket timeout, as a backstop against a probe
+/// task never completing.
+static RTSP_REQUEST_TIMEOUT_MS: u64 = 3000;
+
+/// Load the configured RTSP probing concurrency limit. The file holds a
+/// single integer; a missing or unparsable file falls back to
+/// `DEFAULT_MAX_IN_FLIGHT`.
+fn load_max_in_flight(file: &str) -> usize {
+    File::open(file).ok()
+        .and_then(|file| {
+            let mut line = String::new();
+            match BufReader::new(file).read_line(&mut line) {
+                Ok(_)  => line.trim().parse::<usize>().ok(),
+                Err(_) => None
+            }
+        })
+        .unwrap_or(DEFAULT_MAX_IN_FLIGHT)
+}
 
 /// Discovery error.
 #[derive(Debug, Clone)]
@@ -94,6 +140,361 @@ impl From<io::Error> for DiscoveryError {
 /// Discovery result type alias.
 pub type Result<T> = result::Result<T, DiscoveryError>;
 
+/// RTSP authentication credentials.
+#[derive(Debug, Clone)]
+struct Credentials {
+    username: String,
+    password: String,
+}
+
+impl Credentials {
+    fn new(username: &str, password: &str) -> Credentials {
+        Credentials {
+            username: username.to_string(),
+            password: password.to_string(),
+        }
+    }
+}
+
+/// A parsed `WWW-Authenticate` challenge.
+enum AuthChallenge {
+    Basic,
+    Digest {
+        realm: String,
+        nonce: String,
+        qop: Option<String>,
+        opaque: Option<String>,
+    }
+}
+
+/// Parse a `WWW-Authenticate` header value into an authentication
+/// challenge.
+fn parse_www_authenticate(header: &str) -> Option<AuthChallenge> {
+    let header = header.trim();
+    if header.starts_with("Basic") {
+        Some(AuthChallenge::Basic)
+    } else if header.starts_with("Digest") {
+        let params = parse_auth_params(&header[6..]);
+        match (params.get("realm"), params.get("nonce")) {
+            (Some(realm), Some(nonce)) => Some(AuthChallenge::Digest {
+                realm:  realm.clone(),
+                nonce:  nonce.clone(),
+                qop:    params.get("qop").map(|qop| select_qop(qop)),
+                opaque: params.get("opaque").cloned(),
+            }),
+            _ => None
+        }
+    } else {
+        None
+    }
+}
+
+/// Pick a single `qop` token to use from a (possibly comma-separated)
+/// `qop` challenge value, as RFC 2617 requires the client to echo back
+/// exactly one of the server's offered options. `auth` is preferred over
+/// `auth-int`, since only the former is implemented; otherwise the first
+/// offered token is used.
+fn select_qop(qop: &str) -> String {
+    let mut tokens = qop.split(',').map(|token| token.trim());
+    tokens.clone().find(|token| *token == "auth")
+        .or_else(|| tokens.next())
+        .unwrap_or("auth")
+        .to_string()
+}
+
+/// Parse comma-separated `key="value"` pairs from an authentication
+/// challenge header. Commas inside a quoted value (e.g. a `qop`
+/// challenge of `qop="auth-int,auth"`) do not split the pair.
+fn parse_auth_params(s: &str) -> HashMap<String, String> {
+    let mut res = HashMap::new();
+
+    for part in split_unquoted(s, ',') {
+        let part = part.trim();
+        if let Some(eq) = part.find('=') {
+            let key = part[..eq].trim();
+            let val = part[eq + 1..].trim().trim_matches('"');
+            res.insert(key.to_string(), val.to_string());
+        }
+    }
+
+    res
+}
+
+/// Split `s` on `sep`, ignoring any occurrence of `sep` that falls inside a
+/// double-quoted substring.
+fn split_unquoted(s: &str, sep: char) -> Vec<&str> {
+    let mut res = Vec::new();
+    let mut in_quotes = false;
+    let mut start = 0;
+
+    for (i, c) in s.char_indices() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if c == sep && !in_quotes {
+            res.push(&s[start..i]);
+            start = i + 1;
+        }
+    }
+    res.push(&s[start..]);
+
+    res
+}
+
+/// Build a `Basic` `Authorization` header value for given credentials.
+fn build_basic_auth(creds: &Credentials) -> String {
+    let plain = format!("{}:{}", creds.username, creds.password);
+    format!("Basic {}", base64::encode(plain.as_bytes()))
+}
+
+/// Build a `Digest` `Authorization` header value for given credentials and
+/// challenge, as per RFC 2617.
+fn build_digest_auth(
+    creds: &Credentials,
+    realm: &str,
+    nonce: &str,
+    qop: &Option<String>,
+    opaque: &Option<String>,
+    method: &str,
+    uri: &str) -> String {
+    let ha1 = md5::hex(&md5::compute(
+        format!("{}:{}:{}", creds.username, realm, creds.password).as_bytes()));
+    let ha2 = md5::hex(&md5::compute(
+        format!("{}:{}", method, uri).as_bytes()));
+
+    let (response, qop_part) = match *qop {
+        Some(ref qop) => {
+            let nc     = "00000001";
+            let cnonce = generate_cnonce();
+            let response = md5::hex(&md5::compute(format!(
+                "{}:{}:{}:{}:{}:{}", ha1, nonce, nc, cnonce, qop, ha2).as_bytes()));
+            (response, format!(", qop={}, nc={}, cnonce=\"{}\"", qop, nc, cnonce))
+        }
+        None => {
+            let response = md5::hex(&md5::compute(
+                format!("{}:{}:{}", ha1, nonce, ha2).as_bytes()));
+            (response, String::new())
+        }
+    };
+
+    let opaque_part = match *opaque {
+        Some(ref opaque) => format!(", opaque=\"{}\"", opaque),
+        None             => String::new()
+    };
+
+    format!(
+        "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\", \
+         response=\"{}\"{}{}",
+        creds.username, realm, nonce, uri, response, qop_part, opaque_part)
+}
+
+/// Generate a client nonce for digest authentication.
+fn generate_cnonce() -> String {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    format!("{:08x}", nanos)
+}
+
+/// Extract an embedded `user:pass@` userinfo prefix from an RTSP path, if
+/// present, returning the parsed credentials and the remaining path.
+fn parse_path_userinfo(path: &str) -> (Option<Credentials>, &str) {
+    if let Some(pos) = path.find('@') {
+        let (userinfo, rest) = path.split_at(pos);
+        let rest = &rest[1..];
+        if let Some(colon) = userinfo.find(':') {
+            let (user, pass) = userinfo.split_at(colon);
+            return (Some(Credentials::new(user, &pass[1..])), rest);
+        }
+    }
+
+    (None, path)
+}
+
+/// Load known RTSP credentials keyed by host. Each line of the file has the
+/// form "host:username:password", or "[host]:username:password" when the
+/// host is an IPv6 literal (its own embedded colons would otherwise make
+/// the line ambiguous to split); empty lines and lines starting with '#'
+/// are ignored. A missing file is not an error -- it simply yields no
+/// credentials.
+fn load_rtsp_credentials(file: &str) -> Result<HashMap<String, Credentials>> {
+    let mut res = HashMap::new();
+
+    let file = match File::open(file) {
+        Ok(file) => file,
+        Err(_)   => return Ok(res)
+    };
+
+    let breader = BufReader::new(file);
+
+    for line in breader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some((host, user, pass)) = parse_credentials_line(line) {
+            res.insert(host, Credentials::new(user, pass));
+        }
+    }
+
+    Ok(res)
+}
+
+/// Parse a single `rtsp-credentials` line into a (host, username,
+/// password) triple, handling the bracketed `[ipv6]:user:pass` form
+/// needed to disambiguate an IPv6 literal's own colons from the field
+/// separators.
+fn parse_credentials_line(line: &str) -> Option<(String, &str, &str)> {
+    if line.starts_with('[') {
+        let end = line.find(']')?;
+        let host = &line[1..end];
+        let rest = line[end + 1..].trim_start_matches(':');
+
+        let mut parts = rest.splitn(2, ':');
+        match (parts.next(), parts.next()) {
+            (Some(user), Some(pass)) => Some((host.to_string(), user, pass)),
+            _ => None
+        }
+    } else {
+        let mut parts = line.splitn(3, ':');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(host), Some(user), Some(pass)) =>
+                Some((host.to_string(), user, pass)),
+            _ => None
+        }
+    }
+}
+
+/// Minimal self-contained MD5 implementation (RFC 1321), used for RTSP
+/// Digest authentication.
+mod md5 {
+    const S: [u32; 64] = [
+        7, 12, 17, 22,  7, 12, 17, 22,  7, 12, 17, 22,  7, 12, 17, 22,
+        5,  9, 14, 20,  5,  9, 14, 20,  5,  9, 14, 20,  5,  9, 14, 20,
+        4, 11, 16, 23,  4, 11, 16, 23,  4, 11, 16, 23,  4, 11, 16, 23,
+        6, 10, 15, 21,  6, 10, 15, 21,  6, 10, 15, 21,  6, 10, 15, 21,
+    ];
+
+    fn k() -> [u32; 64] {
+        let mut k = [0u32; 64];
+        for i in 0..64 {
+            k[i] = ((((i + 1) as f64).sin().abs()) * 4294967296.0) as u32;
+        }
+        k
+    }
+
+    /// Compute the MD5 digest of a given message.
+    pub fn compute(message: &[u8]) -> [u8; 16] {
+        let k = k();
+
+        let mut a0: u32 = 0x67452301;
+        let mut b0: u32 = 0xefcdab89;
+        let mut c0: u32 = 0x98badcfe;
+        let mut d0: u32 = 0x10325476;
+
+        let mut msg = message.to_vec();
+        let bit_len = (message.len() as u64).wrapping_mul(8);
+
+        msg.push(0x80);
+        while msg.len() % 64 != 56 {
+            msg.push(0);
+        }
+
+        for i in 0..8 {
+            msg.push(((bit_len >> (8 * i)) & 0xff) as u8);
+        }
+
+        for chunk in msg.chunks(64) {
+            let mut m = [0u32; 16];
+            for i in 0..16 {
+                m[i] = (chunk[i * 4] as u32)
+                    | ((chunk[i * 4 + 1] as u32) << 8)
+                    | ((chunk[i * 4 + 2] as u32) << 16)
+                    | ((chunk[i * 4 + 3] as u32) << 24);
+            }
+
+            let (mut a, mut b, mut c, mut d) = (a0, b0, c0, d0);
+
+            for i in 0..64 {
+                let (f, g) = if i < 16 {
+                    ((b & c) | (!b & d), i)
+                } else if i < 32 {
+                    ((d & b) | (!d & c), (5 * i + 1) % 16)
+                } else if i < 48 {
+                    (b ^ c ^ d, (3 * i + 5) % 16)
+                } else {
+                    (c ^ (b | !d), (7 * i) % 16)
+                };
+
+                let f = f.wrapping_add(a).wrapping_add(k[i]).wrapping_add(m[g]);
+                a = d;
+                d = c;
+                c = b;
+                b = b.wrapping_add(f.rotate_left(S[i]));
+            }
+
+            a0 = a0.wrapping_add(a);
+            b0 = b0.wrapping_add(b);
+            c0 = c0.wrapping_add(c);
+            d0 = d0.wrapping_add(d);
+        }
+
+        let mut digest = [0u8; 16];
+        for (i, word) in [a0, b0, c0, d0].iter().enumerate() {
+            digest[i * 4]     = (word & 0xff) as u8;
+            digest[i * 4 + 1] = ((word >> 8) & 0xff) as u8;
+            digest[i * 4 + 2] = ((word >> 16) & 0xff) as u8;
+            digest[i * 4 + 3] = ((word >> 24) & 0xff) as u8;
+        }
+
+        digest
+    }
+
+    /// Format an MD5 digest as a lowercase hex string.
+    pub fn hex(digest: &[u8; 16]) -> String {
+        digest.iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .concat()
+    }
+}
+
+/// Minimal self-contained base64 encoder, used to build `Basic`
+/// `Authorization` header values.
+mod base64 {
+    const ALPHABET: &'static [u8] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    /// Encode a byte slice as a base64 string (with `=` padding).
+    pub fn encode(input: &[u8]) -> String {
+        let mut res = String::with_capacity((input.len() + 2) / 3 * 4);
+
+        for chunk in input.chunks(3) {
+            let b0 = chunk[0];
+            let b1 = *chunk.get(1).unwrap_or(&0);
+            let b2 = *chunk.get(2).unwrap_or(&0);
+
+            res.push(ALPHABET[(b0 >> 2) as usize] as char);
+            res.push(ALPHABET[(((b0 & 0x03) << 4) | (b1 >> 4)) as usize] as char);
+
+            if chunk.len() > 1 {
+                res.push(ALPHABET[(((b1 & 0x0f) << 2) | (b2 >> 6)) as usize] as char);
+            } else {
+                res.push('=');
+            }
+
+            if chunk.len() > 2 {
+                res.push(ALPHABET[(b2 & 0x3f) as usize] as char);
+            } else {
+                res.push('=');
+            }
+        }
+
+        res
+    }
+}
+
 /// Find all RTSP streams in all local networks.
 pub fn find_rtsp_streams() -> Result<ScanReport> {
     let tc      = pcap::new_threading_context();
@@ -118,50 +519,77 @@ pub fn find_rtsp_streams() -> Result<ScanReport> {
     
     for handle in threads {
         if let Ok(res) = handle.join() {
-            report.merge(try!(res));
+            report.merge(res?);
         } else {
             return Err(DiscoveryError::from("port scanner thread panicked"));
         }
     }
     
-    let rtsp_services = try!(find_rtsp_services(&report));
-    
-    let mut threads = Vec::new();
-    let paths       = Arc::new(try!(load_rtsp_paths(RTSP_PATH_FILE)));
-    
-    for (mac, addr) in rtsp_services {
-        let paths  = paths.clone();
-        let handle = thread::spawn(move || {
-            find_rtsp_paths(mac, addr, &paths)
-        });
-        threads.push(handle);
-    }
-    
-    let mut services = Vec::new();
-    
-    for handle in threads {
-        match handle.join() {
-            Err(_) => return Err(DiscoveryError::from(
-                "path testing thread panicked")),
-            Ok(svcs) => services.extend(try!(svcs))
-        }
-    }
-    
+    let paths         = Arc::new(load_rtsp_paths(RTSP_PATH_FILE)?);
+    let credentials   = Arc::new(load_rtsp_credentials(RTSP_CREDENTIALS_FILE)?);
+    let codecs        = Arc::new(load_supported_codecs(RTSP_CODEC_FILE)?);
+    let max_in_flight = load_max_in_flight(RTSP_CONCURRENCY_FILE);
+
+    let runtime = Runtime::new()?;
+
+    let services = runtime.block_on(probe_rtsp_services(
+        &report, paths.clone(), credentials, codecs, max_in_flight))?;
+
     for svc in services {
         report.add_service(svc);
     }
-    
+
+    for svc in find_passive_rtp_streams(&paths)? {
+        report.add_service(svc);
+    }
+
     Ok(report)
 }
 
+/// Run the bounded, async RTSP-probing pipeline: find which of the open
+/// ports already found by `find_services` are RTSP services, then probe
+/// every (service, path) combination, all capped at `max_in_flight`
+/// concurrent requests regardless of how many sockets or paths there are.
+async fn probe_rtsp_services(
+    report: &ScanReport,
+    paths: Arc<Vec<String>>,
+    credentials: Arc<HashMap<String, Credentials>>,
+    codecs: Arc<HashSet<String>>,
+    max_in_flight: usize) -> Result<Vec<Service>> {
+    let sem = Arc::new(Semaphore::new(max_in_flight));
+
+    let rtsp_services = find_rtsp_services(report, sem.clone(), max_in_flight).await?;
+
+    let results = stream::iter(rtsp_services)
+        .map(|(mac, addr)| {
+            let paths       = paths.clone();
+            let credentials = credentials.clone();
+            let codecs      = codecs.clone();
+            let sem         = sem.clone();
+            async move {
+                find_rtsp_paths(mac, addr, paths, credentials, codecs, sem).await
+            }
+        })
+        .buffer_unordered(max_in_flight)
+        .collect::<Vec<_>>()
+        .await;
+
+    let mut services = Vec::new();
+    for res in results {
+        services.extend(res?);
+    }
+
+    Ok(services)
+}
+
 /// Load all known RTSP path variants from a given file.
 fn load_rtsp_paths(file: &str) -> Result<Vec<String>> {
-    let file      = try!(File::open(file));
+    let file      = File::open(file)?;
     let breader   = BufReader::new(file);
     let mut paths = Vec::new();
     
     for line in breader.lines() {
-        let path = try!(line);
+        let path = line?;
         if !path.starts_with('#') {
             paths.push(path);
         }
@@ -170,34 +598,101 @@ fn load_rtsp_paths(file: &str) -> Result<Vec<String>> {
     Ok(paths)
 }
 
-/// Check if a given service is an RTSP service.
-fn is_rtsp_service(addr: SocketAddr) -> Result<bool> {
-    let mut client = try!(RtspClient::new(addr));
+/// Check if a given service is an RTSP service. This blocks the calling
+/// thread; use the `is_rtsp_service` async wrapper from the probing
+/// pipeline instead.
+fn is_rtsp_service_blocking(addr: SocketAddr) -> Result<bool> {
+    let mut client = RtspClient::new(addr)?;
     client.set_timeout(Some(1000));
     Ok(client.options().is_ok())
 }
 
-/// Check if a given session description contains at least one H.264 or 
-/// a general MPEG4 video stream.
-fn is_supported_service(sdp: &[u8]) -> bool {
-    if let Ok(sdp) = SessionDescription::parse(sdp) {
-        let mut vcodecs   = HashSet::new();
-        let video_streams = sdp.media_descriptions.into_iter()
-            .filter(|md| md.media_type == MediaType::Video);
-        
-        for md in video_streams {
-            for attr in md.attributes {
-                if let Ok(rtpmap) = RTPMap::parse(&attr) {
-                    vcodecs.insert(rtpmap.encoding.to_uppercase());
-                }
-            }
+/// Check if a given service is an RTSP service, running the blocking probe
+/// on a background thread and bounding it with an overall request timeout.
+async fn is_rtsp_service(addr: SocketAddr) -> Result<bool> {
+    let task = task::spawn_blocking(move || is_rtsp_service_blocking(addr));
+
+    match timeout(Duration::from_millis(RTSP_REQUEST_TIMEOUT_MS), task).await {
+        Ok(Ok(res))  => res,
+        Ok(Err(_))   => Err(DiscoveryError::from("RTSP probe task panicked")),
+        Err(_)       => Ok(false)
+    }
+}
+
+/// Default codec allow-list used when no `rtsp-codecs` config file is
+/// present.
+fn default_supported_codecs() -> HashSet<String> {
+    [
+        "H264", "H264-RCDO", "H264-SVC", "MP4V-ES", "MPEG4-GENERIC",
+        "H265", "HEVC", "VP8", "VP9", "AV1",
+    ].iter().map(|codec| codec.to_string()).collect()
+}
+
+/// Load the operator-configurable codec allow-list from a given file, one
+/// codec name per line (case-insensitive; e.g. `H265`, `VP9`,
+/// `MPEG4-GENERIC`, `PCMU`, `OPUS`). Empty lines and lines starting with
+/// '#' are ignored. A missing file falls back to `default_supported_codecs`.
+fn load_supported_codecs(file: &str) -> Result<HashSet<String>> {
+    let file = match File::open(file) {
+        Ok(file) => file,
+        Err(_)   => return Ok(default_supported_codecs())
+    };
+
+    let breader = BufReader::new(file);
+    let mut res = HashSet::new();
+
+    for line in breader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if !line.is_empty() && !line.starts_with('#') {
+            res.insert(line.to_uppercase());
         }
-        
-        vcodecs.contains("H264") ||
-            vcodecs.contains("H264-RCDO") ||
-            vcodecs.contains("H264-SVC") ||
-            vcodecs.contains("MP4V-ES") ||
-            vcodecs.contains("MPEG4-GENERIC")
+    }
+
+    Ok(res)
+}
+
+/// Static mapping from well-known RTP payload type numbers to codec names,
+/// used when an SDP media line does not carry an `rtpmap` attribute for a
+/// given format. Dynamic payload types (96 and above) always require an
+/// explicit `rtpmap` and are not covered here.
+fn static_payload_type_codec(pt: u8) -> Option<&'static str> {
+    match pt {
+        0  => Some("PCMU"),
+        8  => Some("PCMA"),
+        26 => Some("JPEG"),
+        32 => Some("MPV"),
+        33 => Some("MP2T"),
+        _  => None
+    }
+}
+
+/// Collect the codec name advertised for each format (payload type) of a
+/// given SDP media description. A format with an explicit `rtpmap` uses
+/// it; otherwise it falls back to the static payload type table.
+fn media_codecs(md: &MediaDescription) -> HashSet<String> {
+    let mut rtpmaps = HashMap::new();
+
+    for attr in &md.attributes {
+        if let Ok(rtpmap) = RTPMap::parse(attr) {
+            rtpmaps.insert(rtpmap.payload_type, rtpmap.encoding.to_uppercase());
+        }
+    }
+
+    md.formats.iter()
+        .filter_map(|pt| rtpmaps.get(pt).cloned()
+            .or_else(|| static_payload_type_codec(*pt).map(|codec| codec.to_string())))
+        .collect()
+}
+
+/// Check if a given session description contains at least one video or
+/// audio stream whose codec is in the given allow-list.
+fn is_supported_service(sdp: &[u8], codecs: &HashSet<String>) -> bool {
+    if let Ok(sdp) = SessionDescription::parse(sdp) {
+        sdp.media_descriptions.iter()
+            .filter(|md| md.media_type == MediaType::Video ||
+                md.media_type == MediaType::Audio)
+            .any(|md| media_codecs(md).iter().any(|codec| codecs.contains(codec)))
     } else {
         false
     }
@@ -213,32 +708,208 @@ enum DescribeStatus {
     Error
 }
 
-/// Get describe status code for a given RTSP service and path.
-fn get_describe_status(addr: SocketAddr, path: &str) -> Result<DescribeStatus> {
-    let mut client = try!(RtspClient::new(addr));
-    client.set_timeout(Some(1000));
-    if let Ok(response) = client.describe(path) {
-        let header = response.header;
-        let hipcam = match header.get_str("Server") {
-            Some("HiIpcam/V100R003 VodServer/1.0.0") => true,
-            Some("Hipcam RealServer/V1.0")           => true,
-            _ => false
-        };
-        
-        if hipcam && path != "/11" && path != "/12" {
-            Ok(DescribeStatus::NotFound)
-        } else {
-            match header.code {
-                404 => Ok(DescribeStatus::NotFound),
-                401 => Ok(DescribeStatus::Locked),
-                200 if is_supported_service(&response.body) => 
-                    Ok(DescribeStatus::Ok),
-                200 => Ok(DescribeStatus::Unsupported),
-                _   => Ok(DescribeStatus::Error)
-            }
+/// Issue a request through `issue`, and if the server challenges it with a
+/// 401, retry once with a Basic or Digest `Authorization` header computed
+/// from the challenge and the given credentials. The digest response is
+/// computed against `method`/`uri` as RFC 2617 requires, so this must be
+/// called separately for each RTSP method rather than reusing an
+/// `Authorization` header computed for a different request.
+fn request_authenticated<F>(
+    client: &mut RtspClient,
+    credentials: Option<&Credentials>,
+    method: &str,
+    uri: &str,
+    mut issue: F) -> rtsp::Result<rtsp::Response>
+    where F: FnMut(&mut RtspClient) -> rtsp::Result<rtsp::Response> {
+    let response = issue(client)?;
+
+    if response.header.code != 401 {
+        return Ok(response);
+    }
+
+    let auth_value = credentials.and_then(|creds| {
+        response.header.get_str("WWW-Authenticate")
+            .and_then(parse_www_authenticate)
+            .map(|challenge| match challenge {
+                AuthChallenge::Basic => build_basic_auth(creds),
+                AuthChallenge::Digest { realm, nonce, qop, opaque } =>
+                    build_digest_auth(
+                        creds, &realm, &nonce, &qop, &opaque,
+                        method, uri)
+            })
+    });
+
+    match auth_value {
+        Some(auth_value) => {
+            client.set_authorization(Some(auth_value));
+            issue(client)
         }
+        None => Ok(response)
+    }
+}
+
+/// Issue a DESCRIBE request for a given path, retrying once with a
+/// Basic/Digest `Authorization` header on a 401 challenge.
+fn describe_authenticated(
+    client: &mut RtspClient,
+    path: &str,
+    credentials: Option<&Credentials>) -> rtsp::Result<rtsp::Response> {
+    request_authenticated(client, credentials, "DESCRIBE", path,
+        |client| client.describe(path))
+}
+
+/// Get describe status code for a given RTSP service and path, running
+/// the blocking RTSP exchange on a background thread and bounding it with
+/// an overall request timeout.
+async fn get_describe_status(
+    addr: SocketAddr,
+    path: String,
+    credentials: Option<Credentials>,
+    codecs: Arc<HashSet<String>>) -> Result<DescribeStatus> {
+    let task = task::spawn_blocking(move || {
+        get_describe_status_blocking(addr, &path, credentials.as_ref(), &codecs)
+    });
+
+    match timeout(Duration::from_millis(RTSP_REQUEST_TIMEOUT_MS), task).await {
+        Ok(Ok(res)) => res,
+        Ok(Err(_))  => Err(DiscoveryError::from("RTSP probe task panicked")),
+        Err(_)      => Ok(DescribeStatus::Error)
+    }
+}
+
+/// Blocking implementation of `get_describe_status`. When credentials are
+/// given and the server challenges the initial DESCRIBE with a 401, the
+/// request is retried with a Basic or Digest `Authorization` header
+/// computed from the challenge.
+fn get_describe_status_blocking(
+    addr: SocketAddr,
+    path: &str,
+    credentials: Option<&Credentials>,
+    codecs: &HashSet<String>) -> Result<DescribeStatus> {
+    let mut client = RtspClient::new(addr)?;
+    client.set_timeout(Some(1000));
+
+    let response = match describe_authenticated(&mut client, path, credentials) {
+        Ok(response) => response,
+        Err(_)       => return Ok(DescribeStatus::Error)
+    };
+
+    let header = response.header;
+    let hipcam = match header.get_str("Server") {
+        Some("HiIpcam/V100R003 VodServer/1.0.0") => true,
+        Some("Hipcam RealServer/V1.0")           => true,
+        _ => false
+    };
+
+    if hipcam && path != "/11" && path != "/12" {
+        Ok(DescribeStatus::NotFound)
     } else {
-        Ok(DescribeStatus::Error)
+        match header.code {
+            404 => Ok(DescribeStatus::NotFound),
+            // Only credit a still-401 response as `Locked` when we actually
+            // had credentials to retry with; without any configured, a 401
+            // here tells us nothing about whether the path is reachable.
+            401 if credentials.is_some() => Ok(DescribeStatus::Locked),
+            401 => Ok(DescribeStatus::Error),
+            200 if is_supported_service(&response.body, codecs) =>
+                Ok(DescribeStatus::Ok),
+            200 => Ok(DescribeStatus::Unsupported),
+            _   => Ok(DescribeStatus::Error)
+        }
+    }
+}
+
+/// Confirm that a path already accepted by DESCRIBE can actually be
+/// streamed, running the blocking SETUP/PLAY/TEARDOWN exchange on a
+/// background thread and bounding it with an overall request timeout.
+async fn confirm_rtsp_stream(
+    addr: SocketAddr,
+    path: String,
+    credentials: Option<Credentials>,
+    codecs: Arc<HashSet<String>>) -> Result<bool> {
+    let task = task::spawn_blocking(move || {
+        confirm_rtsp_stream_blocking(addr, &path, credentials.as_ref(), &codecs)
+    });
+
+    match timeout(Duration::from_millis(RTSP_REQUEST_TIMEOUT_MS), task).await {
+        Ok(Ok(res)) => res,
+        Ok(Err(_))  => Err(DiscoveryError::from("RTSP probe task panicked")),
+        Err(_)      => Ok(false)
+    }
+}
+
+/// Blocking implementation of `confirm_rtsp_stream`: runs a SETUP/PLAY/
+/// TEARDOWN sequence against the first supported video media advertised
+/// by the path's SDP. SETUP requests interleaved TCP transport so that no
+/// UDP ports need to be opened for the confirmation itself.
+fn confirm_rtsp_stream_blocking(
+    addr: SocketAddr,
+    path: &str,
+    credentials: Option<&Credentials>,
+    codecs: &HashSet<String>) -> Result<bool> {
+    let mut client = RtspClient::new(addr)?;
+    client.set_timeout(Some(1000));
+
+    let response = match describe_authenticated(&mut client, path, credentials) {
+        Ok(response) => response,
+        Err(_)       => return Ok(false)
+    };
+
+    if response.header.code != 200 {
+        return Ok(false);
+    }
+
+    let sdp = match SessionDescription::parse(&response.body) {
+        Ok(sdp) => sdp,
+        Err(_)  => return Ok(false)
+    };
+
+    let media = sdp.media_descriptions.into_iter()
+        .find(|md| md.media_type == MediaType::Video &&
+            media_codecs(md).iter().any(|codec| codecs.contains(codec)));
+
+    let media = match media {
+        Some(media) => media,
+        None        => return Ok(false)
+    };
+
+    let control = media_control_url(path, &media);
+
+    let setup_ok = match request_authenticated(
+        &mut client, credentials, "SETUP", &control,
+        |client| client.setup(&control, "RTP/AVP/TCP;interleaved=0-1")) {
+        Ok(response) => response.header.code == 200 &&
+            response.header.get_str("Session").is_some(),
+        Err(_) => false
+    };
+
+    if !setup_ok {
+        return Ok(false);
+    }
+
+    let play_ok = request_authenticated(
+        &mut client, credentials, "PLAY", path, |client| client.play(path))
+        .map(|response| response.header.code == 200)
+        .unwrap_or(false);
+
+    let _ = request_authenticated(
+        &mut client, credentials, "TEARDOWN", path, |client| client.teardown(path));
+
+    Ok(play_ok)
+}
+
+/// Resolve the control URL for a given SDP media description, falling
+/// back to the DESCRIBE path when the media itself does not advertise a
+/// `control` attribute.
+fn media_control_url(path: &str, media: &MediaDescription) -> String {
+    let control = media.attributes.iter()
+        .find(|attr| attr.starts_with("control:"))
+        .map(|attr| attr["control:".len()..].to_string());
+
+    match control {
+        Some(ref control) if control.starts_with('/') => control.clone(),
+        Some(control) => format!("{}/{}", path.trim_end_matches('/'), control),
+        None => path.to_string()
     }
 }
 
@@ -250,19 +921,26 @@ fn find_services(
     ports: &PortCollection) -> Result<ScanReport> {
     let mut report  = ScanReport::new();
     
-    for (mac, ip) in try!(Ipv4ArpScanner::scan_device(pc.clone(), device)) {
+    for (mac, ip) in Ipv4ArpScanner::scan_device(pc.clone(), device)? {
         report.add_host(mac, IpAddr::V4(ip), HINFO_FLAG_ARP);
     }
-    
-    for (mac, ip) in try!(IcmpScanner::scan_device(pc.clone(), device)) {
+
+    for (mac, ip) in IcmpScanner::scan_device(pc.clone(), device)? {
         report.add_host(mac, IpAddr::V4(ip), HINFO_FLAG_ICMP);
     }
-    
+
+    // Neighbor Solicitation to the solicited-node multicast address plus
+    // Router Solicitation to learn on-link prefixes, so that IPv6-only
+    // cameras are found the same way ARP/ICMP find IPv4 ones.
+    for (mac, ip) in Icmpv6NdScanner::scan_device(pc.clone(), device)? {
+        report.add_host(mac, IpAddr::V6(ip), HINFO_FLAG_ICMP);
+    }
+
     let open_ports = {
         let hosts = report.hosts()
             .map(|host| (host.mac_addr, host.ip_addr));
         
-        try!(find_open_ports(pc, device, hosts, ports))
+        find_open_ports(pc, device, hosts, ports)?
     };
     
     for (mac, addr) in open_ports {
@@ -276,78 +954,127 @@ fn find_services(
 fn find_open_ports<H: IntoIterator<Item=(MacAddr, IpAddr)>>(
     pc: pcap::ThreadingContext,
     device: &EthernetDevice,
-    hosts: H, 
+    hosts: H,
     ports: &PortCollection) -> Result<Vec<(MacAddr, SocketAddr)>> {
-    let hosts = hosts.into_iter()
+    let hosts = hosts.into_iter().collect::<Vec<_>>();
+
+    let v4_hosts = hosts.iter().cloned()
         .filter_map(|(mac, ip)| match ip {
             IpAddr::V4(ip) => Some((mac, ip)),
             _              => None
         });
-    
-    let res = try!(TcpPortScanner::scan_ipv4_hosts(pc, device, hosts, ports))
+
+    let v6_hosts = hosts.iter().cloned()
+        .filter_map(|(mac, ip)| match ip {
+            IpAddr::V6(ip) => Some((mac, ip)),
+            _              => None
+        });
+
+    let mut res = TcpPortScanner::scan_ipv4_hosts(pc.clone(), device, v4_hosts, ports)?
         .into_iter()
         .map(|(mac, ip, p)| (mac, SocketAddr::V4(SocketAddrV4::new(ip, p))))
         .collect::<Vec<_>>();
-    
+
+    // Neighbor Discovery mostly surfaces link-local (fe80::/10) addresses,
+    // which are only meaningful together with the originating interface;
+    // without the scope id here, connecting back to them would fail.
+    let scope_id = device.index();
+
+    let v6_res = TcpPortScanner::scan_ipv6_hosts(pc, device, v6_hosts, ports)?
+        .into_iter()
+        .map(|(mac, ip, p)| (mac, SocketAddr::V6(SocketAddrV6::new(ip, p, 0, scope_id))))
+        .collect::<Vec<_>>();
+
+    res.extend(v6_res);
+
     Ok(res)
 }
 
-/// Find all RTSP services among a given set of sockets.
-fn find_rtsp_services(
-    report: &ScanReport) -> Result<Vec<(MacAddr, SocketAddr)>> {
-    let mut threads = Vec::new();
-    let mut res     = Vec::new();
-    
-    for (mac, addr) in report.socket_addrs() {
-        let handle = thread::spawn(move || {
-            (mac, addr, is_rtsp_service(addr))
-        });
-        threads.push(handle);
-    }
-    
-    for handle in threads {
-        if let Ok((mac, addr, rtsp)) = handle.join() {
-            if try!(rtsp) {
-                res.push((mac, addr));
+/// Find all RTSP services among a given set of sockets, probing at most
+/// `max_in_flight` sockets concurrently.
+async fn find_rtsp_services(
+    report: &ScanReport,
+    sem: Arc<Semaphore>,
+    max_in_flight: usize) -> Result<Vec<(MacAddr, SocketAddr)>> {
+    let candidates = report.socket_addrs().collect::<Vec<_>>();
+
+    let results = stream::iter(candidates)
+        .map(|(mac, addr)| {
+            let sem = sem.clone();
+            async move {
+                let _permit = sem.acquire_owned().await;
+                (mac, addr, is_rtsp_service(addr).await)
             }
-        } else {
-            return Err(DiscoveryError::from("RTSP service testing thread panicked"));
+        })
+        .buffer_unordered(max_in_flight)
+        .collect::<Vec<_>>()
+        .await;
+
+    let mut res = Vec::new();
+
+    for (mac, addr, rtsp) in results {
+        if rtsp? {
+            res.push((mac, addr));
         }
     }
-    
+
     Ok(res)
 }
 
-/// Find all available RTSP paths for a given RTSP service.
-fn find_rtsp_paths(
-    mac: MacAddr, 
-    addr: SocketAddr, 
-    paths: &[String]) -> Result<Vec<Service>> {
+/// Find all available RTSP paths for a given RTSP service, bounding the
+/// number of in-flight DESCRIBE/SETUP/PLAY exchanges across the whole
+/// probing pipeline via the given shared semaphore.
+async fn find_rtsp_paths(
+    mac: MacAddr,
+    addr: SocketAddr,
+    paths: Arc<Vec<String>>,
+    credentials: Arc<HashMap<String, Credentials>>,
+    codecs: Arc<HashSet<String>>,
+    sem: Arc<Semaphore>) -> Result<Vec<Service>> {
     let mut ok          = Vec::new();
+    let mut unconfirmed = Vec::new();
     let mut unsupported = Vec::new();
     let mut locked      = false;
-    
-    for path in paths {
-        match try!(get_describe_status(addr, path)) {
-            DescribeStatus::Ok          => ok.push(path.to_string()),
+
+    for path in paths.iter() {
+        let (embedded, path) = parse_path_userinfo(path);
+        let creds = embedded.or_else(
+            || credentials.get(&addr.ip().to_string()).cloned());
+
+        let _permit = sem.acquire().await;
+
+        match get_describe_status(
+            addr, path.to_string(), creds.clone(), codecs.clone()).await? {
+            DescribeStatus::Ok => {
+                match confirm_rtsp_stream(
+                    addr, path.to_string(), creds, codecs.clone()).await? {
+                    true  => ok.push(path.to_string()),
+                    false => unconfirmed.push(path.to_string())
+                }
+            }
             DescribeStatus::Unsupported => unsupported.push(path.to_string()),
             DescribeStatus::Locked      => locked = true,
             _ => ()
         }
     }
-    
+
     let mut res = ok.into_iter()
         .map(|path| Service::RTSP(mac, addr, path))
         .collect::<Vec<_>>();
-    
+
     let unsupported = unsupported.into_iter()
         .map(|path| Service::UnsupportedRTSP(mac, addr, path))
         .collect::<Vec<_>>();
-    
+
+    let unconfirmed = unconfirmed.into_iter()
+        .map(|path| Service::UnconfirmedRTSP(mac, addr, path))
+        .collect::<Vec<_>>();
+
     res.extend(unsupported);
-    
-    // Some RTSP servers respond with RTSP 200 to all paths even though they 
-    // cannot stream from all the paths. We should treat them as unknown RTSP 
+    res.extend(unconfirmed);
+
+    // Some RTSP servers respond with RTSP 200 to all paths even though they
+    // cannot stream from all the paths. We should treat them as unknown RTSP
     // services.
     if res.len() == paths.len() {
         res.clear();
@@ -360,6 +1087,395 @@ fn find_rtsp_paths(
     if res.is_empty() {
         res.push(Service::UnknownRTSP(mac, addr));
     }
-    
+
     Ok(res)
 }
+
+/// How long to passively listen on each interface for RTP/RTCP traffic
+/// before classifying what was observed.
+static PASSIVE_CAPTURE_MS: u64 = 5000;
+
+/// A single RTP or RTCP observation extracted from a captured UDP packet.
+#[derive(Debug)]
+enum RtpObservation {
+    Rtp { payload_type: u8, sequence: u16, timestamp: u32 },
+    Rtcp,
+}
+
+/// Classify a UDP payload as RTCP (a Sender/Receiver Report, packet type
+/// 200 or 201, corroborated by a consistent length field) or otherwise as
+/// RTP for any payload type, including dynamic ones (96+) negotiated via
+/// SDP -- the caller is expected to corroborate RTP classification across
+/// packets of the same flow (e.g. monotonic sequence/timestamp growth).
+fn classify_rtp_rtcp(payload: &[u8]) -> Option<RtpObservation> {
+    if payload.len() >= 8 {
+        let version     = payload[0] >> 6;
+        let packet_type = payload[1];
+        if version == 2 && (packet_type == 200 || packet_type == 201) {
+            // The RTP payload-type byte can also land on 200/201 (marker
+            // bit set plus PT 72/73), so corroborate with the RTCP length
+            // field: it must account for exactly the bytes captured for
+            // this to plausibly be a real RTCP packet rather than RTP.
+            let length        = ((payload[2] as usize) << 8) | (payload[3] as usize);
+            let expected_size = (length + 1) * 4;
+            if expected_size == payload.len() {
+                return Some(RtpObservation::Rtcp);
+            }
+        }
+    }
+
+    if payload.len() >= 12 {
+        let version      = payload[0] >> 6;
+        let payload_type = payload[1] & 0x7f;
+        if version == 2 {
+            let sequence  = ((payload[2] as u16) << 8) | (payload[3] as u16);
+            let timestamp = ((payload[4] as u32) << 24) |
+                ((payload[5] as u32) << 16) |
+                ((payload[6] as u32) << 8)  |
+                (payload[7] as u32);
+
+            return Some(RtpObservation::Rtp {
+                payload_type: payload_type,
+                sequence:     sequence,
+                timestamp:    timestamp,
+            });
+        }
+    }
+
+    None
+}
+
+/// Compare two 16-bit RTP sequence numbers, accounting for wraparound, and
+/// return true if `a` comes after `b`.
+fn seq_follows(a: u16, b: u16) -> bool {
+    a != b && a.wrapping_sub(b) < 0x8000
+}
+
+/// A flow of RTP or RTCP traffic sharing the same source/destination
+/// 5-tuple, observed during passive discovery.
+struct PassiveFlow {
+    mac: MacAddr,
+    src: SocketAddr,
+    payload_type: Option<u8>,
+}
+
+/// Minimum number of packets a flow must contribute before it is trusted
+/// enough to be reported, to weed out a handful of stray UDP packets that
+/// happen to share the RTP/RTCP header shape.
+static MIN_PASSIVE_PACKETS: usize = 3;
+
+/// Passively listen on all given devices for a fixed time window and group
+/// the UDP traffic observed into RTP/RTCP flows, discarding RTP flows that
+/// do not look like a monotonically increasing RTP sequence (to weed out
+/// unrelated UDP noise that happens to share the header shape). A flow with
+/// fewer than two RTP packets is kept, without a payload type, only when it
+/// holds at least `MIN_PASSIVE_PACKETS` RTCP reports.
+fn find_passive_rtp_flows(
+    tc: pcap::ThreadingContext,
+    devices: &[EthernetDevice]) -> Result<Vec<PassiveFlow>> {
+    let mut threads = Vec::new();
+
+    for dev in devices {
+        let tc     = tc.clone();
+        let dev    = dev.clone();
+        let handle = thread::spawn(move || {
+            pcap::capture_udp(tc, &dev, PASSIVE_CAPTURE_MS)
+        });
+        threads.push(handle);
+    }
+
+    let mut packets = Vec::new();
+
+    for handle in threads {
+        match handle.join() {
+            Ok(res) => packets.extend(res?),
+            Err(_)  => return Err(DiscoveryError::from(
+                "passive capture thread panicked"))
+        }
+    }
+
+    let mut flows: HashMap<(MacAddr, SocketAddr, SocketAddr), Vec<RtpObservation>> =
+        HashMap::new();
+
+    for (mac, src, dst, payload) in packets {
+        if let Some(obs) = classify_rtp_rtcp(&payload) {
+            flows.entry((mac, src, dst)).or_insert_with(Vec::new).push(obs);
+        }
+    }
+
+    let mut res = Vec::new();
+
+    for ((mac, src, _dst), observations) in flows {
+        let mut rtcp_packets = 0;
+        let mut rtp_samples  = Vec::new();
+
+        for obs in &observations {
+            match *obs {
+                RtpObservation::Rtcp => rtcp_packets += 1,
+                RtpObservation::Rtp { payload_type, sequence, timestamp } =>
+                    rtp_samples.push((payload_type, sequence, timestamp))
+            }
+        }
+
+        if rtp_samples.len() < 2 {
+            if rtcp_packets >= MIN_PASSIVE_PACKETS {
+                res.push(PassiveFlow {
+                    mac: mac, src: src,
+                    payload_type: None,
+                });
+            }
+            continue;
+        }
+
+        let monotonic = rtp_samples.windows(2).all(|w| {
+            let (_, seq0, ts0) = w[0];
+            let (_, seq1, ts1) = w[1];
+            seq_follows(seq1, seq0) && ts1 >= ts0
+        });
+
+        if monotonic && rtp_samples.len() >= MIN_PASSIVE_PACKETS {
+            res.push(PassiveFlow {
+                mac: mac, src: src,
+                payload_type: Some(rtp_samples[0].0),
+            });
+        }
+    }
+
+    Ok(res)
+}
+
+/// Build a payload-type -> codec name map from an RTSP service's SDP, so
+/// that dynamic RTP payload types observed passively can be mapped back to
+/// a codec when a co-located RTSP service advertises them.
+fn describe_payload_types(
+    addr: SocketAddr,
+    path: &str,
+    credentials: Option<&Credentials>) -> Result<HashMap<u8, String>> {
+    let mut client = RtspClient::new(addr)?;
+    client.set_timeout(Some(1000));
+
+    let response = match describe_authenticated(&mut client, path, credentials) {
+        Ok(response) => response,
+        Err(_)       => return Ok(HashMap::new())
+    };
+
+    let mut res = HashMap::new();
+
+    if let Ok(sdp) = SessionDescription::parse(&response.body) {
+        for md in sdp.media_descriptions {
+            for attr in md.attributes {
+                if let Ok(rtpmap) = RTPMap::parse(&attr) {
+                    res.insert(rtpmap.payload_type, rtpmap.encoding.to_uppercase());
+                }
+            }
+        }
+    }
+
+    Ok(res)
+}
+
+/// Resolve a codec name for a dynamic RTP payload type by asking every
+/// known RTSP path at the same address, stopping at the first one whose
+/// SDP advertises it.
+fn codec_for_payload_type(
+    addr: SocketAddr,
+    paths: &[String],
+    credentials: &HashMap<String, Credentials>,
+    payload_type: u8) -> Option<String> {
+    for path in paths {
+        let (embedded, path) = parse_path_userinfo(path);
+        let creds = embedded.as_ref()
+            .or_else(|| credentials.get(&addr.ip().to_string()));
+
+        if let Ok(map) = describe_payload_types(addr, path, creds) {
+            if let Some(codec) = map.get(&payload_type) {
+                return Some(codec.clone());
+            }
+        }
+    }
+
+    None
+}
+
+/// Passively discover RTP/RTCP senders on every local network by sniffing
+/// traffic instead of actively probing hosts. This finds cameras that are
+/// already streaming (e.g. to an NVR) without sending them a single
+/// request. Static payload types are resolved directly; dynamic ones
+/// (96+) are resolved via the SDP of any co-located RTSP service found at
+/// the same address.
+fn find_passive_rtp_streams(rtsp_paths: &[String]) -> Result<Vec<Service>> {
+    let tc          = pcap::new_threading_context();
+    let devices     = EthernetDevice::list();
+    let flows       = find_passive_rtp_flows(tc, &devices)?;
+    let credentials = load_rtsp_credentials(RTSP_CREDENTIALS_FILE)?;
+
+    let res = flows.into_iter()
+        .map(|flow| {
+            let codec = flow.payload_type.and_then(|pt| {
+                static_payload_type_codec(pt).map(|codec| codec.to_string())
+                    .or_else(|| codec_for_payload_type(flow.src, rtsp_paths, &credentials, pt))
+            });
+
+            Service::PassiveRTP(flow.mac, flow.src, codec)
+        })
+        .collect();
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn md5_matches_known_vectors() {
+        assert_eq!(md5::hex(&md5::compute(b"")),
+            "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(md5::hex(&md5::compute(b"abc")),
+            "900150983cd24fb0d6963f7d28e17f72");
+    }
+
+    #[test]
+    fn base64_matches_known_vectors() {
+        assert_eq!(base64::encode(b"admin:secret"), "YWRtaW46c2VjcmV0");
+        assert_eq!(base64::encode(b"f"), "Zg==");
+        assert_eq!(base64::encode(b"fo"), "Zm8=");
+        assert_eq!(base64::encode(b"foo"), "Zm9v");
+    }
+
+    #[test]
+    fn select_qop_prefers_auth_over_auth_int() {
+        assert_eq!(select_qop("auth,auth-int"), "auth");
+        assert_eq!(select_qop("auth-int,auth"), "auth");
+        assert_eq!(select_qop("auth-int"), "auth-int");
+    }
+
+    #[test]
+    fn parse_www_authenticate_basic() {
+        match parse_www_authenticate("Basic realm=\"x\"") {
+            Some(AuthChallenge::Basic) => (),
+            _ => panic!("expected Basic challenge")
+        }
+    }
+
+    #[test]
+    fn parse_www_authenticate_digest_picks_single_qop() {
+        let header = "Digest realm=\"cam\", nonce=\"abc123\", \
+            qop=\"auth,auth-int\", opaque=\"xyz\"";
+        match parse_www_authenticate(header) {
+            Some(AuthChallenge::Digest { realm, nonce, qop, opaque }) => {
+                assert_eq!(realm, "cam");
+                assert_eq!(nonce, "abc123");
+                assert_eq!(qop, Some("auth".to_string()));
+                assert_eq!(opaque, Some("xyz".to_string()));
+            }
+            _ => panic!("expected Digest challenge")
+        }
+    }
+
+    #[test]
+    fn parse_www_authenticate_digest_qop_survives_embedded_comma() {
+        let header = "Digest realm=\"cam\", nonce=\"abc123\", \
+            qop=\"auth-int,auth\"";
+        match parse_www_authenticate(header) {
+            Some(AuthChallenge::Digest { qop, .. }) =>
+                assert_eq!(qop, Some("auth".to_string())),
+            _ => panic!("expected Digest challenge")
+        }
+    }
+
+    #[test]
+    fn parse_credentials_line_handles_ipv4_and_hostnames() {
+        let (host, user, pass) =
+            parse_credentials_line("192.168.1.10:admin:secret").unwrap();
+        assert_eq!(host, "192.168.1.10");
+        assert_eq!(user, "admin");
+        assert_eq!(pass, "secret");
+
+        let (host, user, pass) =
+            parse_credentials_line("camera.local:viewer:pw").unwrap();
+        assert_eq!(host, "camera.local");
+        assert_eq!(user, "viewer");
+        assert_eq!(pass, "pw");
+    }
+
+    #[test]
+    fn parse_credentials_line_handles_bracketed_ipv6() {
+        let (host, user, pass) =
+            parse_credentials_line("[::1]:admin:secret").unwrap();
+        assert_eq!(host, "::1");
+        assert_eq!(user, "admin");
+        assert_eq!(pass, "secret");
+
+        let (host, user, pass) =
+            parse_credentials_line("[2001:db8::1]:admin:secret").unwrap();
+        assert_eq!(host, "2001:db8::1");
+        assert_eq!(user, "admin");
+        assert_eq!(pass, "secret");
+    }
+
+    #[test]
+    fn parse_credentials_line_rejects_malformed_lines() {
+        assert!(parse_credentials_line("no-colon-here").is_none());
+        assert!(parse_credentials_line("[::1]:onlyuser").is_none());
+    }
+
+    #[test]
+    fn classify_rtp_rtcp_detects_static_and_dynamic_payload_types() {
+        let mut rtp = vec![0x80, 0, 0, 1,  0, 0, 0, 10,  0, 0, 0, 0];
+        rtp.extend_from_slice(&[0; 4]);
+        match classify_rtp_rtcp(&rtp) {
+            Some(RtpObservation::Rtp { payload_type, sequence, timestamp }) => {
+                assert_eq!(payload_type, 0);
+                assert_eq!(sequence, 1);
+                assert_eq!(timestamp, 10);
+            }
+            other => panic!("expected Rtp observation, got {:?}", other)
+        }
+
+        // A dynamic payload type (96, the one virtually every H.264 RTSP
+        // camera negotiates via `a=rtpmap:96 H264/90000`) must still be
+        // recognized as RTP, not silently dropped.
+        let h264 = vec![0x80, 96, 0, 1,  0, 0, 0, 10,  0, 0, 0, 0];
+        match classify_rtp_rtcp(&h264) {
+            Some(RtpObservation::Rtp { payload_type, .. }) =>
+                assert_eq!(payload_type, 96),
+            other => panic!("expected Rtp observation, got {:?}", other)
+        }
+
+        assert!(classify_rtp_rtcp(&[0; 4]).is_none());
+    }
+
+    #[test]
+    fn classify_rtp_rtcp_detects_rtcp_by_consistent_length_field() {
+        // version=2, packet_type=200 (SR), length=1 (32-bit words minus
+        // one) => total size must be (1 + 1) * 4 == 8 bytes to be trusted
+        // as RTCP.
+        let rtcp = vec![0x80, 200, 0, 1,  0, 0, 0, 0];
+        match classify_rtp_rtcp(&rtcp) {
+            Some(RtpObservation::Rtcp) => (),
+            other => panic!("expected Rtcp observation, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn classify_rtp_rtcp_does_not_mistake_rtp_marker_pt72_for_rtcp() {
+        // marker bit set + PT 72 also encodes to byte1 == 200, but the
+        // length field won't line up with an 8-byte RTCP packet, so this
+        // must fall through to RTP classification instead.
+        let mut rtp = vec![0x80, 200, 0, 1,  0, 0, 0, 10,  0, 0, 0, 0];
+        rtp.extend_from_slice(&[0; 4]);
+        match classify_rtp_rtcp(&rtp) {
+            Some(RtpObservation::Rtp { payload_type, .. }) => assert_eq!(payload_type, 72),
+            other => panic!("expected Rtp observation, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn seq_follows_handles_wraparound() {
+        assert!(seq_follows(2, 1));
+        assert!(!seq_follows(1, 2));
+        assert!(seq_follows(0, 65535));
+        assert!(!seq_follows(5, 5));
+    }
+}